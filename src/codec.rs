@@ -0,0 +1,46 @@
+use serde::Serialize;
+
+use crate::messages::{ExternalPayload, Message};
+
+/// The wire format `Runner` uses to turn one line of input into a
+/// `Message<ExternalPayload>` and a `Message<P>` back into one line of
+/// output. [`Runner::with_codec`](crate::runner::Runner::with_codec) accepts
+/// any codec for `send`/`rpc`, but only a `Runner<JsonCodec>` can drive a
+/// `Node`: `Node` takes `&mut Runner<'_, JsonCodec>`, and `run` and
+/// `run_with_injected_events` are defined for `JsonCodec` alone.
+///
+/// `Body<Payload>` flattens `Payload` into the same JSON object as
+/// `msg_id`/`in_reply_to`, and `ExternalPayload` is internally tagged on
+/// `type` — that combination only round-trips through a self-describing
+/// format (one where the `Deserialize` impl can ask "what's the next field
+/// called", e.g. JSON or any other that models maps as maps instead of a
+/// fixed-layout byte sequence). A `Codec` impl must be self-describing for
+/// the same reason.
+///
+/// A second, compact binary codec (bincode/postcard) was on the original
+/// wishlist here, but is closed as infeasible as specified: both the
+/// flatten and the internal tagging individually require a self-describing
+/// deserializer, and neither postcard nor bincode implement one. Getting a
+/// binary option would mean giving `ExternalPayload` a second, plain-enum
+/// wire representation and converting to/from it, which is a bigger
+/// redesign than this trait — not something to take on silently alongside
+/// an unrelated fix.
+pub trait Codec: Clone + Send + 'static {
+    fn decode(&self, line: &str) -> anyhow::Result<Message<ExternalPayload>>;
+    fn encode<P: Serialize>(&self, message: &Message<P>) -> anyhow::Result<String>;
+}
+
+/// Line-delimited JSON, the format Maelstrom itself speaks — the default,
+/// and the only codec `main.rs` actually drives a node with.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonCodec;
+
+impl Codec for JsonCodec {
+    fn decode(&self, line: &str) -> anyhow::Result<Message<ExternalPayload>> {
+        Ok(serde_json::from_str(line)?)
+    }
+
+    fn encode<P: Serialize>(&self, message: &Message<P>) -> anyhow::Result<String> {
+        Ok(serde_json::to_string(message)?)
+    }
+}
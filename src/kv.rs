@@ -0,0 +1,88 @@
+use serde_json::Value;
+
+use crate::{
+    messages::{ErrorCode, ExternalPayload},
+    runner::Runner,
+};
+
+/// A handle to one of Maelstrom's reserved key-value services
+/// (`seq-kv`/`lin-kv`/`lww-kv`), reached like any other node via `runner`.
+#[allow(dead_code)]
+pub struct Kv {
+    dest: &'static str,
+}
+
+#[allow(dead_code)]
+impl Kv {
+    pub fn seq() -> Self {
+        Kv { dest: "seq-kv" }
+    }
+
+    pub fn lin() -> Self {
+        Kv { dest: "lin-kv" }
+    }
+
+    pub fn lww() -> Self {
+        Kv { dest: "lww-kv" }
+    }
+
+    /// `Ok(None)` if the key has never been written; any other failure is
+    /// returned as an error.
+    pub fn read(&self, runner: &mut Runner, key: Value) -> anyhow::Result<Option<Value>> {
+        match runner.rpc(self.dest, &ExternalPayload::KvRead { key }, None)? {
+            ExternalPayload::KvReadOk { value } => Ok(Some(value)),
+            ExternalPayload::Error {
+                code: ErrorCode::KeyDoesNotExist,
+                ..
+            } => Ok(None),
+            other => anyhow::bail!("unexpected reply to kv read: {other:?}"),
+        }
+    }
+
+    pub fn write(&self, runner: &mut Runner, key: Value, value: Value) -> anyhow::Result<()> {
+        match runner.rpc(self.dest, &ExternalPayload::Write { key, value }, None)? {
+            ExternalPayload::WriteOk => Ok(()),
+            other => anyhow::bail!("unexpected reply to kv write: {other:?}"),
+        }
+    }
+
+    /// Atomically swap `key` from `from` to `to`. The inner `Result` surfaces
+    /// [`ErrorCode::KeyDoesNotExist`] (key absent and `create_if_not_exists`
+    /// is `false`) and [`ErrorCode::PreconditionFailed`] (stored value isn't
+    /// `from`) directly, so a caller — e.g. a create-if-not-exists retry
+    /// loop — can match on them instead of parsing an error string; any
+    /// other failure is still the outer `anyhow::Error`.
+    pub fn cas(
+        &self,
+        runner: &mut Runner,
+        key: Value,
+        from: Value,
+        to: Value,
+        create_if_not_exists: bool,
+    ) -> anyhow::Result<Result<(), ErrorCode>> {
+        match runner.rpc(
+            self.dest,
+            &ExternalPayload::Cas {
+                key,
+                from,
+                to,
+                create_if_not_exists: Some(create_if_not_exists),
+            },
+            None,
+        )? {
+            ExternalPayload::CasOk => Ok(Ok(())),
+            ExternalPayload::Error {
+                code: code @ (ErrorCode::KeyDoesNotExist | ErrorCode::PreconditionFailed),
+                ..
+            } => Ok(Err(code)),
+            ExternalPayload::Error { code, text } => {
+                anyhow::bail!(
+                    "cas on {:?} failed ({code:?}): {}",
+                    self.dest,
+                    text.unwrap_or_default()
+                )
+            }
+            other => anyhow::bail!("unexpected reply to kv cas: {other:?}"),
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, de::Error as _};
+use serde_repr::{Deserialize_repr, Serialize_repr};
 
 #[allow(dead_code)]
 pub enum Event {
@@ -24,7 +25,7 @@ pub struct Body<Payload> {
     pub payload: Payload,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "snake_case", tag = "type")]
 pub enum ExternalPayload {
     Init(Init),
@@ -51,6 +52,131 @@ pub enum ExternalPayload {
     Gossip {
         messages: Vec<i32>,
     },
+    GossipOk {
+        messages: Vec<i32>,
+    },
+    // The real seq-kv/lin-kv/lww-kv services speak "read"/"read_ok" on the
+    // wire too, the same tags the broadcast workload's `Read`/`ReadOk`
+    // already use above; `#[derive(Deserialize)]` can't route two variants
+    // off one tag, so `Deserialize` is hand-written below and disambiguates
+    // by shape (a kv read carries "key" and a kv read_ok carries "value";
+    // the broadcast ones don't).
+    #[serde(rename = "read")]
+    KvRead {
+        key: serde_json::Value,
+    },
+    #[serde(rename = "read_ok")]
+    KvReadOk {
+        value: serde_json::Value,
+    },
+    Write {
+        key: serde_json::Value,
+        value: serde_json::Value,
+    },
+    WriteOk,
+    Cas {
+        key: serde_json::Value,
+        from: serde_json::Value,
+        to: serde_json::Value,
+        create_if_not_exists: Option<bool>,
+    },
+    CasOk,
+    Error {
+        code: ErrorCode,
+        text: Option<String>,
+    },
+}
+
+impl<'de> Deserialize<'de> for ExternalPayload {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let value = serde_json::Value::deserialize(deserializer)?;
+        let tag = value
+            .get("type")
+            .and_then(serde_json::Value::as_str)
+            .ok_or_else(|| D::Error::missing_field("type"))?;
+
+        Ok(match tag {
+            "init" => ExternalPayload::Init(from_value(value)?),
+            "init_ok" => ExternalPayload::InitOk,
+            "echo" => ExternalPayload::Echo(from_value(value)?),
+            "echo_ok" => ExternalPayload::EchoOk(from_value(value)?),
+            "generate" => ExternalPayload::Generate,
+            "generate_ok" => ExternalPayload::GenerateOk {
+                id: field(&value, "id")?,
+            },
+            "broadcast" => ExternalPayload::Broadcast {
+                value: field(&value, "message")?,
+            },
+            "broadcast_ok" => ExternalPayload::BroadcastOk,
+            // a kv read request carries "key"; the broadcast workload's own
+            // bodyless `read` request doesn't
+            "read" if value.get("key").is_some() => ExternalPayload::KvRead {
+                key: field(&value, "key")?,
+            },
+            "read" => ExternalPayload::Read,
+            "read_ok" if value.get("value").is_some() => ExternalPayload::KvReadOk {
+                value: field(&value, "value")?,
+            },
+            "read_ok" => ExternalPayload::ReadOk {
+                messages: field(&value, "messages")?,
+            },
+            "topology" => ExternalPayload::Topology {
+                topology: field(&value, "topology")?,
+            },
+            "topology_ok" => ExternalPayload::TopologyOk,
+            "gossip" => ExternalPayload::Gossip {
+                messages: field(&value, "messages")?,
+            },
+            "gossip_ok" => ExternalPayload::GossipOk {
+                messages: field(&value, "messages")?,
+            },
+            "write" => ExternalPayload::Write {
+                key: field(&value, "key")?,
+                value: field(&value, "value")?,
+            },
+            "write_ok" => ExternalPayload::WriteOk,
+            "cas" => ExternalPayload::Cas {
+                key: field(&value, "key")?,
+                from: field(&value, "from")?,
+                to: field(&value, "to")?,
+                create_if_not_exists: optional_field(&value, "create_if_not_exists")?,
+            },
+            "cas_ok" => ExternalPayload::CasOk,
+            "error" => ExternalPayload::Error {
+                code: field(&value, "code")?,
+                text: optional_field(&value, "text")?,
+            },
+            other => return Err(D::Error::unknown_variant(other, &[])),
+        })
+    }
+}
+
+fn from_value<T, E>(value: serde_json::Value) -> Result<T, E>
+where
+    T: serde::de::DeserializeOwned,
+    E: serde::de::Error,
+{
+    serde_json::from_value(value).map_err(E::custom)
+}
+
+fn field<T, E>(value: &serde_json::Value, name: &'static str) -> Result<T, E>
+where
+    T: serde::de::DeserializeOwned,
+    E: serde::de::Error,
+{
+    let raw = value.get(name).ok_or_else(|| E::missing_field(name))?;
+    from_value(raw.clone())
+}
+
+fn optional_field<T, E>(value: &serde_json::Value, name: &'static str) -> Result<Option<T>, E>
+where
+    T: serde::de::DeserializeOwned,
+    E: serde::de::Error,
+{
+    value.get(name).cloned().map(from_value).transpose()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -64,6 +190,43 @@ pub struct Echo {
     pub echo: String,
 }
 
+/// Maelstrom's reserved error codes (0-999); see the protocol's error-codes
+/// reference for the full list. `code` is the bare JSON integer on the wire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+#[repr(u16)]
+pub enum ErrorCode {
+    Timeout = 0,
+    NodeNotFound = 1,
+    NotSupported = 10,
+    TemporarilyUnavailable = 11,
+    MalformedRequest = 12,
+    Crash = 13,
+    Abort = 14,
+    KeyDoesNotExist = 20,
+    KeyAlreadyExists = 21,
+    PreconditionFailed = 22,
+    TxnConflict = 30,
+}
+
+impl ErrorCode {
+    /// `true` if the failure is transient and the same request might succeed
+    /// on retry (e.g. the peer timed out or crashed); `false` if the request
+    /// itself was invalid and retrying it unchanged won't help.
+    pub fn is_indefinite(self) -> bool {
+        matches!(
+            self,
+            ErrorCode::Timeout | ErrorCode::TemporarilyUnavailable | ErrorCode::Crash
+        )
+    }
+
+    /// The complement of [`is_indefinite`](Self::is_indefinite): `true` if
+    /// the failure is definite, i.e. retrying the same request unchanged
+    /// won't help (e.g. `key_does_not_exist`, `precondition_failed`).
+    pub fn is_definite(self) -> bool {
+        !self.is_indefinite()
+    }
+}
+
 #[allow(dead_code)]
 pub enum InternalPayload {
     Timer,
@@ -1,222 +1,278 @@
-use std::{
-    collections::{HashMap, HashSet},
-    io::{StdoutLock, Write},
-    sync::mpsc::Receiver,
+use std::collections::{HashMap, HashSet};
+
+use log::debug;
+
+use crate::{
+    messages::{ErrorCode, ExternalPayload, Message},
+    runner::Runner,
 };
 
-use anyhow::Context;
+/// Application logic driven by a [`Runner`]: `on_init` sets up any state that
+/// depends on the cluster roster, `on_timer` reacts to periodic wakeups, and
+/// `handle` processes one inbound message. All three reply through `runner`.
+pub trait Node {
+    fn on_init(&mut self, _runner: &mut Runner, _node_id: &str, _cluster: &[String]) {}
+
+    fn on_timer(&mut self, _runner: &mut Runner) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn handle(
+        &mut self,
+        runner: &mut Runner,
+        message: Message<ExternalPayload>,
+    ) -> anyhow::Result<()>;
+}
 
-use crate::messages::{Body, Event, ExternalPayload, InternalPayload, Message};
+// cap on how many timer ticks we'll wait between retransmissions to an
+// unresponsive neighbor, so a long partition doesn't stretch retries out
+// indefinitely
+const MAX_BACKOFF_TICKS: u32 = 8;
 
-#[allow(dead_code)]
-pub struct Node<'n> {
-    node_id: String,
-    cluster: Vec<String>,
-    topology: HashMap<String, Vec<String>>,
-    uniq_msg_id: usize,
-    // ids received from clients in `Broadcast` messages;
-    // gossip them to nodes in our topology
+/// The broadcast workload: gossips values seen via `Broadcast` to the rest of
+/// the cluster so every node's `Read` eventually reflects every value.
+///
+/// Rather than flooding the raw Maelstrom `topology`, each node gossips only
+/// to its neighbors in an `fanout`-ary spanning tree over the cluster's ids
+/// sorted lexicographically (so the smallest id is the root) — O(log n)
+/// hops per message instead of O(n) per broadcast. Gossip is acknowledged:
+/// a value isn't dropped from a neighbor's outstanding set until that
+/// neighbor replies `GossipOk`, and later timer ticks retransmit whatever is
+/// still unacknowledged, so delivery survives a dropped gossip message. A
+/// neighbor that keeps failing to ack is backed off exponentially (capped at
+/// `MAX_BACKOFF_TICKS`) rather than retried every single tick.
+pub struct BroadcastNode {
+    fanout: usize,
+    overlay: Vec<String>,
+    generate_seq: usize,
+    // ids received from clients in `Broadcast` messages or from neighbors'
+    // `Gossip`; gossiped to our overlay neighbors
     broadcast_ids: HashSet<i32>,
-    // track messages sent to us via gossip from other nodes, so we don't send back
-    // the same messages to them when we gossip to them
+    // ids a neighbor told us about via its own `Gossip`, so we don't bounce
+    // them straight back
     known_ids: HashMap<String, HashSet<i32>>,
-    input_stream: Receiver<Event>,
-    output: StdoutLock<'n>,
+    // ids a neighbor has confirmed receiving via `GossipOk`, so we stop
+    // retransmitting them
+    acked_ids: HashMap<String, HashSet<i32>>,
+    // ticks remaining before we'll retry a neighbor that still has
+    // outstanding gossip; counts down to 0 independently of `backoff`, which
+    // is what it gets reloaded from each time it runs out
+    retry_countdown: HashMap<String, u32>,
+    // the retry interval (in ticks) a neighbor is currently on; doubles
+    // (up to `MAX_BACKOFF_TICKS`) each time a retry goes unacked, reset to 1
+    // as soon as it acks
+    backoff: HashMap<String, u32>,
 }
 
-impl<'n> Node<'n> {
-    pub fn new(input: Receiver<Event>, output: StdoutLock<'n>) -> Self {
-        Node {
-            node_id: String::from(""),
-            cluster: Vec::new(),
-            topology: HashMap::new(),
+impl BroadcastNode {
+    pub fn new(fanout: usize) -> Self {
+        BroadcastNode {
+            fanout,
+            overlay: Vec::new(),
+            generate_seq: 0,
             broadcast_ids: HashSet::new(),
-            uniq_msg_id: 0,
             known_ids: HashMap::new(),
-            input_stream: input,
-            output,
+            acked_ids: HashMap::new(),
+            retry_countdown: HashMap::new(),
+            backoff: HashMap::new(),
         }
     }
 
-    pub fn initialize(mut self) -> anyhow::Result<Self> {
-        let event = self.input_stream.recv()?;
-        if let Event::External { message } = event
-            && let ExternalPayload::Init(init) = message.body.payload
-        {
-            let msg_id = Some(self.uniq_msg_id);
-            self.uniq_msg_id += 1;
-            let msg = serde_json::to_string(&Message {
-                src: init.node_id.clone(),
-                dst: message.src.clone(),
-                body: Body {
-                    msg_id,
-                    in_reply_to: message.body.msg_id,
-                    payload: &ExternalPayload::InitOk,
-                },
-            })?;
-            eprintln!("sending message to {}: {}", message.src, msg);
-            writeln!(self.output, "{}", msg).context("serialization failed")?;
-
-            let node_id = init.node_id.to_string();
-            let cluster = init.node_ids.to_vec();
-            // initialize "default" topology to be the full cluster, until we
-            // get a 'topology' message
-            let mut topology: HashMap<String, Vec<String>> = HashMap::new();
-            topology.insert(node_id.clone(), cluster.clone());
-
-            let mut known_ids = HashMap::new();
-            known_ids.insert(node_id.clone(), HashSet::new());
-
-            Ok(Node {
-                node_id,
-                cluster,
-                topology,
-                known_ids,
-                ..self
-            })
-        } else {
-            panic!("expected init message")
+    fn outstanding_for(&self, dest: &str) -> Vec<i32> {
+        let already_has = self
+            .known_ids
+            .get(dest)
+            .into_iter()
+            .chain(self.acked_ids.get(dest));
+        self.broadcast_ids
+            .iter()
+            .filter(|v| !already_has.clone().any(|has| has.contains(v)))
+            .copied()
+            .collect()
+    }
+}
+
+/// Build the `node_id`'s neighbor list in an `fanout`-ary spanning tree over
+/// `sorted_cluster` (the full cluster, sorted lexicographically): a parent
+/// and up to `fanout` children, same layout as an array-backed heap.
+fn spanning_tree_neighbors(sorted_cluster: &[String], node_id: &str, fanout: usize) -> Vec<String> {
+    let Some(i) = sorted_cluster.iter().position(|id| id == node_id) else {
+        return Vec::new();
+    };
+
+    let mut neighbors = Vec::new();
+    if i > 0 {
+        neighbors.push(sorted_cluster[(i - 1) / fanout].clone());
+    }
+    for child in (fanout * i + 1)..=(fanout * i + fanout) {
+        if let Some(id) = sorted_cluster.get(child) {
+            neighbors.push(id.clone());
         }
     }
+    neighbors
+}
+
+impl Node for BroadcastNode {
+    fn on_init(&mut self, _runner: &mut Runner, node_id: &str, cluster: &[String]) {
+        let mut sorted_cluster = cluster.to_vec();
+        sorted_cluster.sort();
+
+        self.overlay = spanning_tree_neighbors(&sorted_cluster, node_id, self.fanout);
+        self.known_ids = self
+            .overlay
+            .iter()
+            .map(|id| (id.clone(), HashSet::new()))
+            .collect();
+        self.acked_ids = self
+            .overlay
+            .iter()
+            .map(|id| (id.clone(), HashSet::new()))
+            .collect();
+        self.retry_countdown = self.overlay.iter().map(|id| (id.clone(), 0)).collect();
+        self.backoff = self.overlay.iter().map(|id| (id.clone(), 1)).collect();
+        debug!("overlay neighbors for {node_id}: {:?}", self.overlay);
+    }
+
+    fn on_timer(&mut self, runner: &mut Runner) -> anyhow::Result<()> {
+        if self.broadcast_ids.is_empty() {
+            return Ok(());
+        }
 
-    pub fn run(&mut self) -> anyhow::Result<()> {
-        loop {
-            match self.input_stream.recv()? {
-                Event::Internal { payload } => match payload {
-                    InternalPayload::Timer => {
-                        eprintln!("received timer wakeup");
-                        if !self.broadcast_ids.is_empty() {
-                            let gossip_targets: Vec<(String, Vec<i32>)> = self
-                                .topology
-                                .get(&self.node_id)
-                                .expect("always have our own topology node")
-                                .iter()
-                                .filter_map(|dest| {
-                                    let messages: Vec<i32> = self
-                                        .broadcast_ids
-                                        .iter()
-                                        .filter(|v| {
-                                            let known = self
-                                                .known_ids
-                                                .get(dest)
-                                                .expect("always have a known_for bucket");
-                                            !known.contains(v)
-                                        })
-                                        .copied()
-                                        .collect();
-                                    if messages.is_empty() {
-                                        None
-                                    } else {
-                                        Some((dest.clone(), messages))
-                                    }
-                                })
-                                .collect();
-
-                            for (dest, messages) in gossip_targets {
-                                eprintln!("gossiping to {dest}");
-                                let gossip = ExternalPayload::Gossip { messages };
-                                self.send_to(&dest, None, &gossip)?;
-                            }
-                        }
-                    }
-                    InternalPayload::Eof => {
-                        eprintln!("received EOF");
-                        break;
-                    }
-                },
-                Event::External { message } => match message.body.payload {
-                    ExternalPayload::Init(_init) => panic!("got `init` but already initialized"),
-                    ExternalPayload::Echo(echo) => {
-                        self.send_to(
-                            &message.src,
-                            message.body.msg_id,
-                            &ExternalPayload::EchoOk(echo),
-                        )?;
-                    }
-                    ExternalPayload::Generate => {
-                        self.send_to(
-                            &message.src,
-                            message.body.msg_id,
-                            &ExternalPayload::GenerateOk {
-                                id: format!("{}-{}", self.node_id, self.uniq_msg_id),
-                            },
-                        )?;
-                    }
-                    ExternalPayload::Broadcast { value } => {
-                        self.send_to(
-                            &message.src,
-                            message.body.msg_id,
-                            &ExternalPayload::BroadcastOk,
-                        )?;
-                        self.broadcast_ids.insert(value);
-                    }
-                    ExternalPayload::Topology { topology } => {
-                        self.topology = topology;
-                        self.known_ids.clear();
-                        self.topology
-                            .keys()
-                            .filter(|n| **n != self.node_id)
-                            .for_each(|k| {
-                                self.known_ids.insert(k.clone(), HashSet::new());
-                            });
-                        eprintln!("new topology: {:?}", self.topology);
-                        self.send_to(
-                            &message.src,
-                            message.body.msg_id,
-                            &ExternalPayload::TopologyOk,
-                        )?;
-                    }
-                    ExternalPayload::Read => self.send_to(
-                        &message.src,
-                        message.body.msg_id,
-                        &ExternalPayload::ReadOk {
-                            messages: self.broadcast_ids.iter().copied().collect(),
-                        },
-                    )?,
-                    ExternalPayload::Gossip { messages } => {
-                        eprintln!("received gossip from {}: {:?}", message.src, messages);
-                        self.broadcast_ids.extend(&messages);
-                        self.known_ids
-                            .get_mut(&message.src)
-                            .expect("always have an entry for node topology")
-                            .extend(messages);
-                        eprintln!(
-                            "known ids for {}: {:?}",
-                            message.src, self.known_ids[&message.src]
-                        );
-                    }
-                    // ignore these ...
-                    ExternalPayload::ReadOk { .. }
-                    | ExternalPayload::InitOk
-                    | ExternalPayload::EchoOk(_)
-                    | ExternalPayload::GenerateOk { .. }
-                    | ExternalPayload::BroadcastOk
-                    | ExternalPayload::TopologyOk => {}
-                },
+        for dest in self.overlay.clone() {
+            let messages = self.outstanding_for(&dest);
+            if messages.is_empty() {
+                continue;
             }
+
+            let countdown = self.retry_countdown.entry(dest.clone()).or_insert(0);
+            if *countdown > 0 {
+                *countdown -= 1;
+                continue;
+            }
+
+            debug!("gossiping {} values to {dest}", messages.len());
+            runner.send(&dest, None, &ExternalPayload::Gossip { messages })?;
+
+            let backoff = self.backoff.entry(dest.clone()).or_insert(1);
+            self.retry_countdown.insert(dest, *backoff);
+            *backoff = (*backoff * 2).min(MAX_BACKOFF_TICKS);
         }
-        eprintln!("finished");
+
         Ok(())
     }
 
-    fn send_to(
+    fn handle(
         &mut self,
-        dst: &str,
-        in_reply_to: Option<usize>,
-        payload: &ExternalPayload,
+        runner: &mut Runner,
+        message: Message<ExternalPayload>,
     ) -> anyhow::Result<()> {
-        let msg_id = Some(self.uniq_msg_id);
-        self.uniq_msg_id += 1;
-        let msg = serde_json::to_string(&Message {
-            src: self.node_id.clone(),
-            dst: dst.to_string(),
-            body: Body {
-                msg_id,
-                in_reply_to,
-                payload,
-            },
-        })?;
-        eprintln!("sending message to {dst}: {msg}");
-        writeln!(self.output, "{}", msg).context("serialization failed")
+        match message.body.payload {
+            ExternalPayload::Init(_) => {
+                runner.send(
+                    &message.src,
+                    message.body.msg_id,
+                    &ExternalPayload::Error {
+                        code: ErrorCode::NotSupported,
+                        text: Some("already initialized".to_string()),
+                    },
+                )?;
+            }
+            ExternalPayload::Echo(echo) => {
+                runner.send(
+                    &message.src,
+                    message.body.msg_id,
+                    &ExternalPayload::EchoOk(echo),
+                )?;
+            }
+            ExternalPayload::Generate => {
+                self.generate_seq += 1;
+                runner.send(
+                    &message.src,
+                    message.body.msg_id,
+                    &ExternalPayload::GenerateOk {
+                        id: format!("{}-{}", runner.node_id(), self.generate_seq),
+                    },
+                )?;
+            }
+            ExternalPayload::Broadcast { value } => {
+                runner.send(
+                    &message.src,
+                    message.body.msg_id,
+                    &ExternalPayload::BroadcastOk,
+                )?;
+                self.broadcast_ids.insert(value);
+            }
+            ExternalPayload::Topology { .. } => {
+                // we gossip over our own spanning-tree overlay rather than
+                // the raw topology Maelstrom hands us, but it still expects
+                // every `topology` to be acknowledged
+                runner.send(
+                    &message.src,
+                    message.body.msg_id,
+                    &ExternalPayload::TopologyOk,
+                )?;
+            }
+            ExternalPayload::Read => {
+                runner.send(
+                    &message.src,
+                    message.body.msg_id,
+                    &ExternalPayload::ReadOk {
+                        messages: self.broadcast_ids.iter().copied().collect(),
+                    },
+                )?;
+            }
+            ExternalPayload::Gossip { messages } => {
+                debug!("received gossip from {}: {:?}", message.src, messages);
+                self.broadcast_ids.extend(&messages);
+                if let Some(known) = self.known_ids.get_mut(&message.src) {
+                    known.extend(messages.iter().copied());
+                }
+                runner.send(&message.src, None, &ExternalPayload::GossipOk { messages })?;
+            }
+            ExternalPayload::GossipOk { messages } => {
+                if let Some(acked) = self.acked_ids.get_mut(&message.src) {
+                    acked.extend(messages);
+                }
+                self.retry_countdown.insert(message.src.clone(), 0);
+                self.backoff.insert(message.src, 1);
+            }
+            // ignore these ...
+            ExternalPayload::ReadOk { .. }
+            | ExternalPayload::InitOk
+            | ExternalPayload::EchoOk(_)
+            | ExternalPayload::GenerateOk { .. }
+            | ExternalPayload::BroadcastOk
+            | ExternalPayload::TopologyOk
+            // replies to our own `Kv` RPCs are consumed directly by
+            // `Runner::rpc`, never routed through here
+            | ExternalPayload::KvReadOk { .. }
+            | ExternalPayload::WriteOk
+            | ExternalPayload::CasOk => {}
+            ExternalPayload::Error { code, text } => {
+                // nothing in this workload currently retries on its own
+                // errors, but log whether a retry would even be worth it
+                debug!(
+                    "{} replied with error {code:?} ({}): {}",
+                    message.src,
+                    if code.is_definite() { "definite" } else { "indefinite" },
+                    text.unwrap_or_default(),
+                );
+            }
+            // the broadcast workload never fields KV requests itself
+            ExternalPayload::KvRead { .. }
+            | ExternalPayload::Write { .. }
+            | ExternalPayload::Cas { .. } => {
+                runner.send(
+                    &message.src,
+                    message.body.msg_id,
+                    &ExternalPayload::Error {
+                        code: ErrorCode::NotSupported,
+                        text: Some("this node does not serve the kv protocol".to_string()),
+                    },
+                )?;
+            }
+        }
+
+        Ok(())
     }
 }
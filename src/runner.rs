@@ -0,0 +1,270 @@
+use std::{
+    collections::VecDeque,
+    io::{StdoutLock, Write},
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use anyhow::Context;
+use log::{debug, error};
+
+use crate::{
+    codec::{Codec, JsonCodec},
+    messages::{Body, ErrorCode, Event, ExternalPayload, InternalPayload, Message},
+    node::Node,
+};
+
+type Output<'a> = Box<dyn Write + 'a>;
+
+/// Owns the process lifecycle: a dedicated thread reads newline-delimited
+/// input off stdin, decodes each line with `C` and forwards it onto an
+/// internal channel, a timer thread injects periodic `Timer` wakeups on the
+/// same channel, and `run` drains that channel to drive a [`Node`] until EOF.
+///
+/// [`Runner::sender`] is a "backdoor" into that channel so application code
+/// (most often a `Node::on_init` hook) can inject its own internal events.
+pub struct Runner<'a, C: Codec = JsonCodec> {
+    node_id: String,
+    uniq_msg_id: usize,
+    events_tx: Sender<Event>,
+    events_rx: Receiver<Event>,
+    // messages pulled off `events_rx` while `rpc` was waiting on a different
+    // `in_reply_to`, to be handed to `node` before any fresh event
+    pending: VecDeque<Message<ExternalPayload>>,
+    output: Output<'a>,
+    codec: C,
+}
+
+impl<'a, C: Codec> Runner<'a, C> {
+    /// `output` is boxed rather than a bare `StdoutLock` so tests can swap
+    /// in an in-memory sink and assert on what a node would have emitted.
+    pub fn with_codec(output: impl Write + 'a, codec: C) -> Self {
+        let (events_tx, events_rx) = mpsc::channel();
+        Runner {
+            node_id: String::new(),
+            uniq_msg_id: 0,
+            events_tx,
+            events_rx,
+            pending: VecDeque::new(),
+            output: Box::new(output),
+            codec,
+        }
+    }
+
+    pub fn node_id(&self) -> &str {
+        &self.node_id
+    }
+
+    /// A clone of the sender driving this runner's event loop, for injecting
+    /// extra internal events.
+    pub fn sender(&self) -> Sender<Event> {
+        self.events_tx.clone()
+    }
+
+    pub fn send(
+        &mut self,
+        dst: &str,
+        in_reply_to: Option<usize>,
+        payload: &ExternalPayload,
+    ) -> anyhow::Result<usize> {
+        let msg_id = self.uniq_msg_id;
+        self.uniq_msg_id += 1;
+
+        let msg = Message {
+            src: self.node_id.clone(),
+            dst: dst.to_string(),
+            body: Body {
+                msg_id: Some(msg_id),
+                in_reply_to,
+                payload,
+            },
+        };
+        let serialized = self.codec.encode(&msg).context("encode message")?;
+        debug!("sending message to {dst}: {serialized}");
+        writeln!(self.output, "{serialized}").context("write to stdout failed")?;
+        Ok(msg_id)
+    }
+
+    /// Send `payload` to `dst` and block until a message whose `in_reply_to`
+    /// matches comes back, returning its payload. Any other message seen
+    /// while waiting is buffered in `pending` so `run`'s main loop still
+    /// sees it afterwards, rather than being dropped.
+    ///
+    /// `timeout` bounds the whole wait; on expiry this returns
+    /// `Ok(ExternalPayload::Error { code: ErrorCode::Timeout, .. })` rather
+    /// than an `Err`, so callers can handle it the same way as any other
+    /// protocol-level failure reply. `None` waits indefinitely.
+    pub fn rpc(
+        &mut self,
+        dst: &str,
+        payload: &ExternalPayload,
+        timeout: Option<Duration>,
+    ) -> anyhow::Result<ExternalPayload> {
+        let msg_id = self.send(dst, None, payload)?;
+        let deadline = timeout.map(|d| Instant::now() + d);
+
+        loop {
+            let event = match deadline {
+                None => self.events_rx.recv()?,
+                Some(deadline) => {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    match self.events_rx.recv_timeout(remaining) {
+                        Ok(event) => event,
+                        Err(RecvTimeoutError::Timeout) => {
+                            return Ok(ExternalPayload::Error {
+                                code: ErrorCode::Timeout,
+                                text: Some(format!("no reply from {dst} to msg_id {msg_id}")),
+                            });
+                        }
+                        Err(RecvTimeoutError::Disconnected) => {
+                            anyhow::bail!("event channel disconnected while awaiting rpc reply")
+                        }
+                    }
+                }
+            };
+
+            match event {
+                Event::External { message } if message.body.in_reply_to == Some(msg_id) => {
+                    return Ok(message.body.payload);
+                }
+                Event::External { message } => self.pending.push_back(message),
+                Event::Internal {
+                    payload: InternalPayload::Eof,
+                } => anyhow::bail!("stdin closed while awaiting rpc reply from {dst}"),
+                Event::Internal {
+                    payload: InternalPayload::Timer,
+                } => continue,
+            }
+        }
+    }
+}
+
+// `run`/`run_with_injected_events`/`drive`/`handshake` drive a `Node`, and
+// `Node` is written against a concrete
+// `&mut Runner<'_, JsonCodec>` rather than being generic over `Codec`
+// itself, so these live in a `JsonCodec`-specific impl block instead of the
+// fully-generic one above.
+impl<'a> Runner<'a, JsonCodec> {
+    /// A `Runner` speaking line-delimited JSON, the format Maelstrom itself
+    /// uses — this is what `main.rs` drives every node with.
+    pub fn new(output: StdoutLock<'a>) -> Self {
+        Self::with_codec(output, JsonCodec)
+    }
+
+    /// Spawn the stdin/timer threads, complete the `init`/`init_ok`
+    /// handshake, then hand every subsequent event to `node` until EOF.
+    pub fn run(mut self, mut node: impl Node, timer_interval: Duration) -> anyhow::Result<()> {
+        let input_thread = spawn_input_thread(self.events_tx.clone(), self.codec);
+        let timer_thread = spawn_timer_thread(self.events_tx.clone(), timer_interval);
+
+        let result = self.drive(&mut node);
+        // dropping `events_rx` makes the timer thread's next send fail, so
+        // it exits instead of blocking the join below forever
+        drop(self);
+
+        input_thread.join().expect("join input thread");
+        timer_thread.join().expect("join timer thread");
+        result
+    }
+
+    /// Like [`run`](Self::run), but without spawning the stdin/timer
+    /// threads: the caller drives `Timer`/`Eof`/external events itself
+    /// through a cloned [`Runner::sender`]. This is what the integration
+    /// test harness uses to exercise timer-dependent behavior (e.g.
+    /// retransmission) without real sleeps or a real stdin.
+    pub fn run_with_injected_events(mut self, mut node: impl Node) -> anyhow::Result<()> {
+        self.drive(&mut node)
+    }
+
+    /// Complete the `init`/`init_ok` handshake, then hand every subsequent
+    /// event to `node` until `Eof`.
+    fn drive(&mut self, node: &mut impl Node) -> anyhow::Result<()> {
+        let (node_id, cluster) = self.handshake()?;
+        node.on_init(self, &node_id, &cluster);
+
+        loop {
+            let event = match self.pending.pop_front() {
+                Some(message) => Event::External { message },
+                None => self.events_rx.recv()?,
+            };
+            match event {
+                Event::Internal {
+                    payload: InternalPayload::Timer,
+                } => node.on_timer(self)?,
+                Event::Internal {
+                    payload: InternalPayload::Eof,
+                } => break,
+                Event::External { message } => node.handle(self, message)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Block for the mandatory `init` message, reply `init_ok`, and record
+    /// `node_id` so subsequent `send`s stamp the right `src`.
+    fn handshake(&mut self) -> anyhow::Result<(String, Vec<String>)> {
+        let event = self.events_rx.recv()?;
+        let Event::External { message } = event else {
+            anyhow::bail!("expected an `init` message, got an internal event");
+        };
+        let init = match &message.body.payload {
+            ExternalPayload::Init(init) => init.clone(),
+            other => anyhow::bail!("expected an `init` message, got {other:?}"),
+        };
+
+        self.node_id = init.node_id.clone();
+        self.send(&message.src, message.body.msg_id, &ExternalPayload::InitOk)?;
+
+        Ok((init.node_id, init.node_ids))
+    }
+}
+
+fn spawn_input_thread<C: Codec>(events: Sender<Event>, codec: C) -> JoinHandle<()> {
+    thread::spawn(move || {
+        let mut buf = String::new();
+        loop {
+            match std::io::stdin().read_line(&mut buf) {
+                Ok(0) => {
+                    let _ = events.send(Event::Internal {
+                        payload: InternalPayload::Eof,
+                    });
+                    break;
+                }
+                Ok(_) => match codec.decode(&buf) {
+                    Ok(message) => {
+                        if events.send(Event::External { message }).is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) => error!("discarding malformed input {buf:?}: {e}"),
+                },
+                Err(e) => {
+                    error!("stdin read error, treating as eof: {e}");
+                    let _ = events.send(Event::Internal {
+                        payload: InternalPayload::Eof,
+                    });
+                    break;
+                }
+            }
+            buf.clear();
+        }
+    })
+}
+
+fn spawn_timer_thread(events: Sender<Event>, interval: Duration) -> JoinHandle<()> {
+    thread::spawn(move || {
+        loop {
+            thread::sleep(interval);
+            if events
+                .send(Event::Internal {
+                    payload: InternalPayload::Timer,
+                })
+                .is_err()
+            {
+                break;
+            }
+        }
+    })
+}
@@ -0,0 +1,129 @@
+//! Exercises each `Codec` directly (bypassing `Runner`) to check that
+//! encoding a message and decoding it back yields the same message — the
+//! property `golden_transcript.rs`'s `Runner`-level coverage doesn't
+//! directly assert.
+
+use bedlam::{
+    codec::{Codec, JsonCodec},
+    messages::{Body, ErrorCode, ExternalPayload, Message},
+};
+
+fn round_trip(codec: &impl Codec, payload: ExternalPayload) {
+    let sent = Message {
+        src: "n1".to_string(),
+        dst: "n2".to_string(),
+        body: Body {
+            msg_id: Some(7),
+            in_reply_to: Some(3),
+            payload,
+        },
+    };
+
+    let encoded = codec.encode(&sent).expect("encode");
+    let decoded = codec.decode(&encoded).expect("decode");
+
+    assert_eq!(decoded.src, sent.src);
+    assert_eq!(decoded.dst, sent.dst);
+    assert_eq!(decoded.body.msg_id, sent.body.msg_id);
+    assert_eq!(decoded.body.in_reply_to, sent.body.in_reply_to);
+    assert_eq!(
+        format!("{:?}", decoded.body.payload),
+        format!("{:?}", sent.body.payload)
+    );
+}
+
+#[test]
+fn json_codec_round_trips_representative_payload_shapes() {
+    round_trip(&JsonCodec, ExternalPayload::InitOk);
+    round_trip(&JsonCodec, ExternalPayload::Broadcast { value: 42 });
+    round_trip(
+        &JsonCodec,
+        ExternalPayload::Gossip {
+            messages: vec![1, 2, 3],
+        },
+    );
+    round_trip(
+        &JsonCodec,
+        ExternalPayload::KvRead {
+            key: serde_json::json!("counter"),
+        },
+    );
+    round_trip(
+        &JsonCodec,
+        ExternalPayload::KvReadOk {
+            value: serde_json::json!(5),
+        },
+    );
+    round_trip(
+        &JsonCodec,
+        ExternalPayload::Error {
+            code: ErrorCode::Crash,
+            text: Some("boom".to_string()),
+        },
+    );
+}
+
+#[test]
+fn error_code_encodes_as_a_bare_json_integer() {
+    // `serde_repr` is what makes this possible; a plain `#[derive(Serialize)]`
+    // would encode the variant name ("crash") instead of the wire-mandated
+    // numeric code, and a future refactor dropping serde_repr wouldn't be
+    // caught by the round-trip test above since that compares two Rust
+    // values, not the wire text itself.
+    let message = Message {
+        src: "n1".to_string(),
+        dst: "n2".to_string(),
+        body: Body {
+            msg_id: Some(1),
+            in_reply_to: Some(0),
+            payload: ExternalPayload::Error {
+                code: ErrorCode::Crash,
+                text: None,
+            },
+        },
+    };
+
+    let encoded = JsonCodec.encode(&message).expect("encode");
+    assert!(
+        encoded.contains(r#""code":13"#),
+        "{encoded:?} does not encode `code` as the bare integer 13"
+    );
+}
+
+#[test]
+fn kv_read_and_read_ok_use_the_literal_maelstrom_wire_tags() {
+    // seq-kv/lin-kv/lww-kv don't know the crate's own `KvRead`/`KvReadOk`
+    // Rust names; on the wire a read request/reply must say "read"/
+    // "read_ok", the same tags the broadcast workload's `Read`/`ReadOk`
+    // already use, and still decode back to the right Rust variant.
+    let read = Message {
+        src: "n1".to_string(),
+        dst: "lin-kv".to_string(),
+        body: Body {
+            msg_id: Some(1),
+            in_reply_to: None,
+            payload: ExternalPayload::KvRead {
+                key: serde_json::json!("counter"),
+            },
+        },
+    };
+    let encoded = JsonCodec.encode(&read).expect("encode");
+    assert!(
+        encoded.contains(r#""type":"read""#),
+        "{encoded:?} does not use the literal \"read\" wire tag"
+    );
+    assert!(matches!(
+        JsonCodec.decode(&encoded).unwrap().body.payload,
+        ExternalPayload::KvRead { .. }
+    ));
+
+    // a real, conformant reply like `{"type":"read_ok","value":5}` must
+    // decode to `KvReadOk`, not fail to parse as the broadcast workload's
+    // `ReadOk { messages }` and get silently discarded.
+    let reply =
+        r#"{"src":"lin-kv","dest":"n1","body":{"in_reply_to":1,"type":"read_ok","value":5}}"#;
+    assert!(matches!(
+        JsonCodec.decode(reply).unwrap().body.payload,
+        ExternalPayload::KvReadOk { value } if value == serde_json::json!(5)
+    ));
+}